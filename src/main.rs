@@ -4,61 +4,247 @@ use clap::{Parser, ValueEnum};
 use nix::sys::signal::{kill, Signal as NixSignal, sigaction, SaFlags, SigAction, SigHandler, SigSet, sigprocmask, SigmaskHow};
 use nix::unistd::Pid;
 use std::error::Error;
+use std::sync::atomic::{AtomicI32, AtomicI64, AtomicUsize, Ordering};
 use std::time::{Duration, Instant};
 use tokio::signal::unix::{signal, SignalKind};
 use libc;
 
-/// 空的信号处理函数
-extern "C" fn empty_signal_handler(_: libc::c_int) {
+/// 无锁环形队列的容量（2 的幂）。单个 AtomicI64 last-write-wins 槽位在信号密集投递时会
+/// 丢掉除最后一条之外的所有 si_value；tokio::signal::unix::Signal 自身也只在"自上次消费
+/// 以来是否有新投递"这一件事上去抖动，多次真实投递可能只换来一次 recv() 唤醒。真正的排队
+/// 保证由内核对实时信号的 FIFO 投递提供：每次真实投递都会调用一次 siginfo_signal_handler，
+/// 这里把每次调用的 si_value 都按顺序写入队列，异步侧用 recv_value() 逐个取出，
+/// 不依赖 recv() 的调用次数与投递次数一一对应
+const QUEUE_CAPACITY: usize = 1 << 16;
+// 这个 const 只用于重复初始化下面的数组，本身从不被直接引用，不会出现 clippy 担心的
+// "每次使用都各自求值一份、共享状态被悄悄拆分" 的问题
+#[allow(clippy::declare_interior_mutable_const)]
+const QUEUE_SLOT_INIT: AtomicI64 = AtomicI64::new(-1);
+static QUEUE_SLOTS: [AtomicI64; QUEUE_CAPACITY] = [QUEUE_SLOT_INIT; QUEUE_CAPACITY];
+static QUEUE_WRITE: AtomicUsize = AtomicUsize::new(0);
+
+/// 最近一次收到的信号的真实发送方 PID（内核为每个信号投递填充的 si_pid，无论是 kill(2)
+/// 还是 sigqueue(2) 送达的都有效）。用于启动握手阶段发现对端的确切 PID——这一步不能依赖
+/// si_value/sigqueue，因为握手的第一条消息在对端 PID 未知时只能走 kill(2) 的组播寻址
+/// （pid 0/-1/<-1），而 sigqueue(2) 不支持这些寻址模式
+static SENDER_PID: AtomicI32 = AtomicI32::new(-1);
+
+/// 携带 siginfo 的信号处理函数（需配合 SA_SIGINFO 注册）：记录发送方 PID，并把 si_value
+/// 按到达顺序追加到队列中
+extern "C" fn siginfo_signal_handler(
+    _signal: libc::c_int,
+    siginfo: *mut libc::siginfo_t,
+    _context: *mut libc::c_void,
+) {
+    if siginfo.is_null() {
+        return;
+    }
+    // Linux 的 libc::sigval 只有 sival_ptr: *mut c_void 一个字段，没有 sival_int；
+    // 发送端把整数塞进指针宽度的字段里，这里原样转回来
+    let value = unsafe { (*siginfo).si_value().sival_ptr } as i64;
+    let sender_pid = unsafe { (*siginfo).si_pid() };
+    SENDER_PID.store(sender_pid, Ordering::Release);
+    let slot = QUEUE_WRITE.fetch_add(1, Ordering::AcqRel) % QUEUE_CAPACITY;
+    QUEUE_SLOTS[slot].store(value, Ordering::Release);
 }
 
-/// 信号屏蔽标志位
-const BLOCK_USR1: i32 = 0x1;
-const BLOCK_USR2: i32 = 0x2;
+/// 从队列中按写入顺序取出下一个尚未被 read_cursor 消费的 si_value；队列暂时为空时
+/// 等待 tokio 的信号通知后重试。read_cursor 由调用方持有，使同一个全局队列可以在
+/// 多次调用间正确地逐个消费，即使多次真实投递被 tokio 合并成了一次 recv() 唤醒
+async fn recv_value(signal: &mut tokio::signal::unix::Signal, read_cursor: &mut usize) -> i64 {
+    loop {
+        let written = QUEUE_WRITE.load(Ordering::Acquire);
+        if *read_cursor < written {
+            let slot = *read_cursor % QUEUE_CAPACITY;
+            let value = QUEUE_SLOTS[slot].load(Ordering::Acquire);
+            *read_cursor += 1;
+            return value;
+        }
+        signal.recv().await;
+    }
+}
 
-/// 设置需要忽略的信号（注册空处理函数）
-fn setup_ignored_signals(flags: i32) -> Result<(), Box<dyn Error>> {
-    // 创建信号动作结构体
-    let signal_action = SigAction::new(
-        SigHandler::Handler(empty_signal_handler), // 空的信号处理函数
-        SaFlags::SA_RESTART,                       // 让系统调用在被信号中断后重启
-        SigSet::empty(),                           // 初始化为空的信号集合
-    );
+/// CLI 可选的信号，覆盖 nix::sys::signal::Signal 的完整具名信号枚举，
+/// 用于 --server-signal/--client-signal 替换掉写死的 SIGUSR1/SIGUSR2
+#[derive(Copy, Clone, Debug, ValueEnum, PartialEq, Eq)]
+enum SignalChoice {
+    Hup,
+    Int,
+    Quit,
+    Ill,
+    Trap,
+    Abrt,
+    Bus,
+    Fpe,
+    Kill,
+    Usr1,
+    Segv,
+    Usr2,
+    Pipe,
+    Alrm,
+    Term,
+    Stkflt,
+    Chld,
+    Cont,
+    Stop,
+    Tstp,
+    Ttin,
+    Ttou,
+    Urg,
+    Xcpu,
+    Xfsz,
+    Vtalrm,
+    Prof,
+    Winch,
+    Io,
+    Pwr,
+    Sys,
+}
 
-    // Ignore SIGUSR1 ?
-    if (flags & BLOCK_USR1) == 0 {
-        // Set signal handler
-        unsafe {
-            sigaction(NixSignal::SIGUSR1, &signal_action)?;
+impl SignalChoice {
+    /// 映射到对应的 nix 信号
+    fn to_nix(self) -> NixSignal {
+        match self {
+            SignalChoice::Hup => NixSignal::SIGHUP,
+            SignalChoice::Int => NixSignal::SIGINT,
+            SignalChoice::Quit => NixSignal::SIGQUIT,
+            SignalChoice::Ill => NixSignal::SIGILL,
+            SignalChoice::Trap => NixSignal::SIGTRAP,
+            SignalChoice::Abrt => NixSignal::SIGABRT,
+            SignalChoice::Bus => NixSignal::SIGBUS,
+            SignalChoice::Fpe => NixSignal::SIGFPE,
+            SignalChoice::Kill => NixSignal::SIGKILL,
+            SignalChoice::Usr1 => NixSignal::SIGUSR1,
+            SignalChoice::Segv => NixSignal::SIGSEGV,
+            SignalChoice::Usr2 => NixSignal::SIGUSR2,
+            SignalChoice::Pipe => NixSignal::SIGPIPE,
+            SignalChoice::Alrm => NixSignal::SIGALRM,
+            SignalChoice::Term => NixSignal::SIGTERM,
+            SignalChoice::Stkflt => NixSignal::SIGSTKFLT,
+            SignalChoice::Chld => NixSignal::SIGCHLD,
+            SignalChoice::Cont => NixSignal::SIGCONT,
+            SignalChoice::Stop => NixSignal::SIGSTOP,
+            SignalChoice::Tstp => NixSignal::SIGTSTP,
+            SignalChoice::Ttin => NixSignal::SIGTTIN,
+            SignalChoice::Ttou => NixSignal::SIGTTOU,
+            SignalChoice::Urg => NixSignal::SIGURG,
+            SignalChoice::Xcpu => NixSignal::SIGXCPU,
+            SignalChoice::Xfsz => NixSignal::SIGXFSZ,
+            SignalChoice::Vtalrm => NixSignal::SIGVTALRM,
+            SignalChoice::Prof => NixSignal::SIGPROF,
+            SignalChoice::Winch => NixSignal::SIGWINCH,
+            SignalChoice::Io => NixSignal::SIGIO,
+            SignalChoice::Pwr => NixSignal::SIGPWR,
+            SignalChoice::Sys => NixSignal::SIGSYS,
         }
     }
 
-    // Ignore SIGUSR2 ?
-    if (flags & BLOCK_USR2) == 0 {
-        // Set signal handler
-        unsafe {
-            sigaction(NixSignal::SIGUSR2, &signal_action)?;
+    /// 映射到 tokio 异步信号接收器使用的 SignalKind
+    fn to_signal_kind(self) -> SignalKind {
+        SignalKind::from_raw(self.to_nix() as i32)
+    }
+}
+
+/// 拒绝无法被捕获/屏蔽的保留信号（SIGKILL、SIGSTOP 的语义由内核保留，sigaction/sigprocmask 均拒绝它们）
+fn reject_unblockable(choice: SignalChoice) -> Result<(), Box<dyn Error>> {
+    match choice {
+        SignalChoice::Kill | SignalChoice::Stop => Err(format!(
+            "{:?} cannot be caught or blocked and is not usable as a ping-pong signal",
+            choice.to_nix()
+        )
+        .into()),
+        _ => Ok(()),
+    }
+}
+
+/// 实时信号相对 SIGRTMIN 的偏移：服务器->客户端(ping)与客户端->服务器(pong)各占一个实时信号编号
+/// （排队投递的保证详见 QUEUE_CAPACITY 上的说明）
+const RT_PING_OFFSET: i32 = 0;
+const RT_PONG_OFFSET: i32 = 1;
+
+/// 计算实际的实时信号编号（SIGRTMIN()是运行时求值的，不能用作 const）。
+/// libc::SIGRTMIN() 本身是安全的 Rust 包装（内部才是 unsafe 的 C 调用），不需要再包一层 unsafe
+fn rt_signal(offset: i32) -> i32 {
+    libc::SIGRTMIN() + offset
+}
+
+/// 为指定的原始信号编号安装携带 siginfo 的处理函数。
+/// nix::sys::signal::Signal 只覆盖具名标准信号，无法表示任意实时信号编号，这里直接使用 libc。
+fn register_rt_siginfo_handler(signum: i32) -> Result<(), Box<dyn Error>> {
+    unsafe {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        action.sa_sigaction = siginfo_signal_handler as *const () as usize;
+        action.sa_flags = libc::SA_SIGINFO | libc::SA_RESTART;
+        libc::sigemptyset(&mut action.sa_mask);
+        if libc::sigaction(signum, &action, std::ptr::null_mut()) != 0 {
+            return Err(Box::new(std::io::Error::last_os_error()));
         }
     }
+    Ok(())
+}
 
+/// 屏蔽指定的原始实时信号编号，交由 tokio 的异步接收器接管投递
+fn block_rt_signal(signum: i32) -> Result<(), Box<dyn Error>> {
+    unsafe {
+        let mut set: libc::sigset_t = std::mem::zeroed();
+        libc::sigemptyset(&mut set);
+        libc::sigaddset(&mut set, signum);
+        if libc::sigprocmask(libc::SIG_BLOCK, &set, std::ptr::null_mut()) != 0 {
+            return Err(Box::new(std::io::Error::last_os_error()));
+        }
+    }
     Ok(())
 }
 
-/// 设置需要屏蔽的信号
-fn setup_blocked_signals(flags: i32) -> Result<(), Box<dyn Error>> {
-    // 创建信号集合
-    let mut mask = SigSet::empty();
+/// 设置服务器端实时信号：注册并屏蔽 pong 信号（自己接收的一侧）
+fn setup_server_rt_signals() -> Result<(), Box<dyn Error>> {
+    register_rt_siginfo_handler(rt_signal(RT_PONG_OFFSET))?;
+    block_rt_signal(rt_signal(RT_PONG_OFFSET))?;
+    std::thread::sleep(std::time::Duration::from_micros(1000));
+    Ok(())
+}
 
-    // Block SIGUSR1
-    if (flags & BLOCK_USR1) != 0 {
-        mask.add(NixSignal::SIGUSR1);
-    }
+/// 设置客户端实时信号：注册并屏蔽 ping 信号（自己接收的一侧）；pong 信号也注册处理函数，
+/// 因为握手时以 PID 0 广播的 pong 同样会投递给客户端自己，未处理的实时信号默认会终止进程
+fn setup_client_rt_signals() -> Result<(), Box<dyn Error>> {
+    register_rt_siginfo_handler(rt_signal(RT_PING_OFFSET))?;
+    register_rt_siginfo_handler(rt_signal(RT_PONG_OFFSET))?;
+    block_rt_signal(rt_signal(RT_PING_OFFSET))?;
+    std::thread::sleep(std::time::Duration::from_micros(1000));
+    Ok(())
+}
+
+/// 通过原始 sigqueue 向目标 PID 投递携带数据的实时信号；与 [`send_signal`] 不同，只接受 pid > 0
+/// 的单个进程，不支持组播寻址（见 [`send_queued_signal`]）
+fn send_rt_signal_with_value(pid: i32, signum: i32, value: i64) -> Result<(), Box<dyn Error>> {
+    send_queued_signal(pid, signum, value)
+}
 
-    // Block SIGUSR2
-    if (flags & BLOCK_USR2) != 0 {
-        mask.add(NixSignal::SIGUSR2);
+/// 设置需要忽略的信号：统一注册可读取 si_value 的 SA_SIGINFO 处理函数，
+/// 因为即使是普通模式也需要在启动握手阶段从 si_pid 中取出对端 PID（见 [`SENDER_PID`]）。
+/// own_signal 是本端监听、之后会被屏蔽转交给 tokio 的信号；peer_signal 是本端只发送、从不等待的信号。
+fn setup_ignored_signals(own_signal: NixSignal, peer_signal: NixSignal) -> Result<(), Box<dyn Error>> {
+    // 创建信号动作结构体
+    let signal_action = SigAction::new(
+        SigHandler::SigAction(siginfo_signal_handler), // 携带 siginfo 的处理函数，可读取 si_value
+        SaFlags::SA_RESTART | SaFlags::SA_SIGINFO,      // 让系统调用重启，并请求 siginfo_t
+        SigSet::empty(),
+    );
+
+    unsafe {
+        sigaction(own_signal, &signal_action)?;
+        sigaction(peer_signal, &signal_action)?;
     }
 
+    Ok(())
+}
+
+/// 设置需要屏蔽的信号：只屏蔽本端监听的信号，交由 tokio 的异步接收器接管投递
+fn setup_blocked_signals(own_signal: NixSignal) -> Result<(), Box<dyn Error>> {
+    // 创建信号集合
+    let mut mask = SigSet::empty();
+    mask.add(own_signal);
+
     // Change signal mask
     sigprocmask(SigmaskHow::SIG_BLOCK, Some(&mask), None)?;
 
@@ -66,26 +252,26 @@ fn setup_blocked_signals(flags: i32) -> Result<(), Box<dyn Error>> {
 }
 
 /// 设置信号处理和屏蔽
-fn setup_signals(flags: i32) -> Result<(), Box<dyn Error>> {
+fn setup_signals(own_signal: NixSignal, peer_signal: NixSignal) -> Result<(), Box<dyn Error>> {
     // 设置需要忽略的信号
-    setup_ignored_signals(flags)?;
+    setup_ignored_signals(own_signal, peer_signal)?;
 
     // 设置需要屏蔽的信号
-    setup_blocked_signals(flags)?;
+    setup_blocked_signals(own_signal)?;
 
     Ok(())
 }
 
-/// 设置服务器端信号屏蔽：屏蔽SIGUSR1，忽略SIGUSR2
-fn setup_server_signals() -> Result<(), Box<dyn Error>> {
-    setup_signals(BLOCK_USR1)?;
+/// 设置服务器端信号屏蔽：屏蔽 server_signal（由客户端发送、服务器监听），忽略 client_signal
+fn setup_server_signals(server_signal: NixSignal, client_signal: NixSignal) -> Result<(), Box<dyn Error>> {
+    setup_signals(server_signal, client_signal)?;
     std::thread::sleep(std::time::Duration::from_micros(1000));
     Ok(())
 }
 
-/// 设置客户端信号屏蔽：忽略SIGUSR1，屏蔽SIGUSR2
-fn setup_client_signals() -> Result<(), Box<dyn Error>> {
-    setup_signals(BLOCK_USR2)?;
+/// 设置客户端信号屏蔽：屏蔽 client_signal（由服务器发送、客户端监听），忽略 server_signal
+fn setup_client_signals(client_signal: NixSignal, server_signal: NixSignal) -> Result<(), Box<dyn Error>> {
+    setup_signals(client_signal, server_signal)?;
     std::thread::sleep(std::time::Duration::from_micros(1000));
     Ok(())
 }
@@ -100,22 +286,43 @@ struct Benchmarks {
     sum: Duration,
     squared_sum: f64,
     count: usize,
+    dropped: usize,
+    /// 按 seq % window 索引的环形缓冲区，记录窗口模式下每个在途消息的发送时刻
+    start_times: Vec<Instant>,
+    window: usize,
 }
 
 impl Benchmarks {
-    /// 创建新的基准测试结构体
-    fn new() -> Self {
+    /// 创建新的基准测试结构体，window 决定了窗口模式下环形缓冲区的大小（非窗口模式下传 1 即可）
+    fn new(window: usize) -> Self {
+        let now = Instant::now();
         Self {
-            total_start: Instant::now(),
-            single_start: Instant::now(),
+            total_start: now,
+            single_start: now,
             minimum: Duration::from_secs(u64::MAX),
             maximum: Duration::from_nanos(0),
             sum: Duration::from_nanos(0),
             squared_sum: 0.0,
             count: 0,
+            dropped: 0,
+            start_times: vec![now; window.max(1)],
+            window: window.max(1),
         }
     }
-    
+
+    /// 记录窗口模式下某个序列号对应的消息发出时刻
+    fn record_start(&mut self, seq: i64) {
+        let slot = (seq as usize) % self.window;
+        self.start_times[slot] = Instant::now();
+    }
+
+    /// 依据环形缓冲区中记录的发出时刻，计算某个序列号对应消息的往返延迟并更新统计
+    fn record_finish(&mut self, seq: i64) {
+        let slot = (seq as usize) % self.window;
+        let elapsed = self.start_times[slot].elapsed();
+        self.update(elapsed);
+    }
+
     /// 更新基准测试数据
     fn update(&mut self, duration: Duration) {
         self.minimum = self.minimum.min(duration);
@@ -124,7 +331,12 @@ impl Benchmarks {
         self.squared_sum += duration.as_nanos() as f64 * duration.as_nanos() as f64;
         self.count += 1;
     }
-    
+
+    /// 记录一次丢失的消息（发送失败，或收到的序列号与期望不符）
+    fn record_drop(&mut self) {
+        self.dropped += 1;
+    }
+
     /// 评估基准测试结果
     fn evaluate(&self, args: &Args) {
         let total_time = self.total_start.elapsed();
@@ -146,17 +358,60 @@ impl Benchmarks {
         println!("Standard deviation: {:.3} us", sigma / 1000.0);
         println!("Message rate:       {:.0} msg/s", message_rate);
         println!("Message rate:       {:.3} MB/s", message_rate_mb);
+        println!("Dropped messages:   {}", self.dropped);
         println!("=====================================");
     }
 }
 
-/// 发送信号到目标 PID
-fn send_signal(pid: u32, signal: NixSignal) -> Result<(), Box<dyn Error>> {
-    let nix_pid = Pid::from_raw(pid as i32);
+/// 发送信号到目标 PID，遵循 kill(2) 的寻址语义：
+/// pid > 0 发给单个进程；pid == 0 发给调用者所在的进程组；
+/// pid == -1 发给调用者有权限发送的所有进程；pid < -1 发给 PID 为 |pid| 的进程组
+fn send_signal(pid: i32, signal: NixSignal) -> Result<(), Box<dyn Error>> {
+    let nix_pid = Pid::from_raw(pid);
     kill(nix_pid, signal)?;
     Ok(())
 }
 
+/// 通过原始 kill(2) 发送不带负载的信号，寻址语义与 [`send_signal`] 相同，但接受任意原始
+/// 信号编号——用于实时信号这类 nix::sys::signal::Signal 无法具名表示的场景
+fn send_raw_signal(pid: i32, signum: i32) -> Result<(), Box<dyn Error>> {
+    let ret = unsafe { libc::kill(pid as libc::pid_t, signum) };
+    if ret != 0 {
+        return Err(Box::new(std::io::Error::last_os_error()));
+    }
+    Ok(())
+}
+
+/// 通过 libc::sigqueue 发送携带数据的排队信号，value 会作为 si_value 被对端的 siginfo 处理函数读取；
+/// 与 [`send_signal`] 不同，只接受 pid > 0 的单个进程，不支持组播寻址（见 [`send_queued_signal`]）。
+/// nix 并未封装 sigqueue(3)（`nix::sys::signal` 里没有这个符号），这里直接调用 libc；
+/// Linux 的 libc::sigval 只有 sival_ptr: *mut c_void 字段，没有 sival_int，因此把整数值
+/// 塞进指针宽度的整数里在两端原样解释
+fn send_signal_with_value(pid: i32, signal: NixSignal, value: i64) -> Result<(), Box<dyn Error>> {
+    send_queued_signal(pid, signal as i32, value)
+}
+
+/// [`send_signal_with_value`]/[`send_rt_signal_with_value`] 共用的底层 sigqueue(3) 调用。
+/// 与 kill(2) 不同，sigqueue(2) 只接受指向单个已存在进程的 pid>0，不支持 pid 0/-1/<-1
+/// 的组播寻址（内核对这些值一律返回 ESRCH），因此这里显式拒绝，而不是让调用方永远等不到回复
+fn send_queued_signal(pid: i32, signum: i32, value: i64) -> Result<(), Box<dyn Error>> {
+    if pid <= 0 {
+        return Err(format!(
+            "sigqueue(2) requires a single existing pid > 0, got {pid}; \
+             group/broadcast addressing is only supported by plain kill(2)"
+        )
+        .into());
+    }
+    let sigval = libc::sigval {
+        sival_ptr: value as *mut libc::c_void,
+    };
+    let ret = unsafe { libc::sigqueue(pid as libc::pid_t, signum, sigval) };
+    if ret != 0 {
+        return Err(Box::new(std::io::Error::last_os_error()));
+    }
+    Ok(())
+}
+
 #[derive(Parser, Debug)]
 struct Args {
     /// ping-pong 次数
@@ -169,6 +424,35 @@ struct Args {
     /// 运行模式：server / client / test
     #[arg(long, short, value_enum, default_value_t = Mode::Server)]
     mode: Mode,
+
+    /// 启用基于 sigqueue 的数据传输模式：每个 ping 通过 si_value 携带单调序列号，接收端校验收到的值与期望值一致
+    #[arg(long)]
+    payload: bool,
+
+    /// 使用 POSIX 实时信号(SIGRTMIN..SIGRTMAX)代替 SIGUSR1/2
+    #[arg(long)]
+    realtime: bool,
+
+    /// 显式指定对端 PID，遵循 kill(2) 寻址语义（>0 单个进程，0 自身进程组，-1 所有有权限的进程，<-1 指定进程组）。
+    /// --payload/--realtime/--window>1 下的数据消息经 sigqueue(2) 发送，只接受 >0 的单个进程。
+    /// 不提供时通过启动握手自动发现对端的确切 PID：客户端的首个握手信号发往自身进程组（PID 0），
+    /// 因此此时双方需处于同一进程组；握手之后双方按确切 PID 通信
+    #[arg(long, allow_hyphen_values = true)]
+    target_pid: Option<i32>,
+
+    /// 服务器监听、由客户端发送的信号（默认 SIGUSR1），--realtime 模式下忽略此项
+    #[arg(long, value_enum, default_value_t = SignalChoice::Usr1)]
+    server_signal: SignalChoice,
+
+    /// 客户端监听、由服务器发送的信号（默认 SIGUSR2），--realtime 模式下忽略此项
+    #[arg(long, value_enum, default_value_t = SignalChoice::Usr2)]
+    client_signal: SignalChoice,
+
+    /// 流水线窗口深度：服务器最多同时有 window 个 ping 在途而不必等待每一个的回复，
+    /// 通过 si_value 携带的序列号与按 seq % window 索引的环形缓冲区匹配回复来记录每条消息的延迟。
+    /// 大于 1 时自动启用实时信号
+    #[arg(long, default_value_t = 1)]
+    window: usize,
 }
 
 #[derive(Copy, Clone, Debug, ValueEnum, PartialEq)]
@@ -178,30 +462,89 @@ enum Mode {
 }
 
 async fn run_server(args: &Args) -> Result<(), Box<dyn Error>> {
-    setup_server_signals()?;
-
-    // 创建信号接收器
-    let mut sigusr1 = signal(SignalKind::user_defined1())?;
+    let server_signal = args.server_signal.to_nix();
+    let client_signal = args.client_signal.to_nix();
+    // 窗口模式依赖实时信号（见 QUEUE_CAPACITY 上的说明）
+    let use_rt = args.realtime || args.window > 1;
+
+    let mut sigusr1 = if use_rt {
+        setup_server_rt_signals()?;
+        signal(SignalKind::from_raw(rt_signal(RT_PONG_OFFSET)))?
+    } else {
+        reject_unblockable(args.server_signal)?;
+        reject_unblockable(args.client_signal)?;
+        setup_server_signals(server_signal, client_signal)?;
+        signal(args.server_signal.to_signal_kind())?
+    };
+    let mut read_cursor: usize = 0;
 
     // 等待初始信号
     eprintln!("[SERVER] Waiting for initial signal from client...");
-    sigusr1.recv().await;
+    recv_value(&mut sigusr1, &mut read_cursor).await;
     eprintln!("[SERVER] Received initial signal from client!");
 
+    // 确定之后通信的目标 PID：显式指定时直接使用；否则内核已经把客户端的真实 PID 填进了
+    // si_pid（无论初始信号是通过 kill(2) 还是 sigqueue(2) 送达的都有效），这里读出来，
+    // 并用同样不带负载的方式回复一个信号，客户端照此发现服务器的 PID；
+    // 不能用 si_value/sigqueue 传回自己的 PID，因为此时客户端的 PID 还未知、只能广播寻址，
+    // 而 sigqueue(2) 不支持 pid<=0 的广播寻址（见 [`send_queued_signal`]）
+    let target_pid = if let Some(target_pid) = args.target_pid {
+        target_pid
+    } else {
+        let client_pid = SENDER_PID.load(Ordering::Acquire);
+        eprintln!("[SERVER] Discovered client PID {} via handshake", client_pid);
+
+        if use_rt {
+            send_raw_signal(client_pid, rt_signal(RT_PING_OFFSET))?;
+        } else {
+            send_signal(client_pid, client_signal)?;
+        }
+
+        client_pid
+    };
+
+    if args.window > 1 {
+        return run_server_windowed(args, &mut sigusr1, &mut read_cursor, target_pid).await;
+    }
+
     // 设置基准测试
-    let mut bench = Benchmarks::new();
+    let mut bench = Benchmarks::new(1);
 
     for message in 0..args.count {
         bench.single_start = Instant::now();
 
-        // eprintln!("[SERVER] Sending SIGUSR2 to client (message: {})..", message + 1);
-        let _ = send_signal(0, NixSignal::SIGUSR2);
+        if args.realtime {
+            let seq = message as i64;
+            // 发送本身也可能失败（例如高 --count 下 RLIMIT_SIGPENDING 耗尽），这种情况下
+            // 永远不会有回复到达，必须就地记为丢失，而不是还去 recv_value() 死等
+            if send_rt_signal_with_value(target_pid, rt_signal(RT_PING_OFFSET), seq).is_err() {
+                bench.record_drop();
+            } else {
+                let received = recv_value(&mut sigusr1, &mut read_cursor).await;
+                if received != seq {
+                    bench.record_drop();
+                }
+            }
+        } else if args.payload {
+            let seq = message as i64;
+            if send_signal_with_value(target_pid, client_signal, seq).is_err() {
+                bench.record_drop();
+            } else {
+                let received = recv_value(&mut sigusr1, &mut read_cursor).await;
+                if received != seq {
+                    bench.record_drop();
+                }
+            }
+        } else {
+            // eprintln!("[SERVER] Sending SIGUSR2 to client (message: {})..", message + 1);
+            let _ = send_signal(target_pid, client_signal);
+
+            // 等待响应信号
+            // eprintln!("[SERVER] Waiting for SIGUSR1 from client (message: {})..", message + 1);
+            sigusr1.recv().await;
+            // eprintln!("[SERVER] Received SIGUSR1 from client (message: {})", message + 1);
+        }
 
-        // 等待响应信号
-        // eprintln!("[SERVER] Waiting for SIGUSR1 from client (message: {})..", message + 1);
-        sigusr1.recv().await;
-        // eprintln!("[SERVER] Received SIGUSR1 from client (message: {})", message + 1);
-        
         let total_duration = bench.single_start.elapsed();
 
         bench.update(total_duration);
@@ -211,33 +554,131 @@ async fn run_server(args: &Args) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+/// 流水线/窗口模式：服务器最多同时保持 args.window 个 ping 在途，通过 recv_value() 逐个把
+/// 队列中的序列号（见 QUEUE_CAPACITY 上的说明）与按 seq % window 索引的环形缓冲区匹配
+async fn run_server_windowed(
+    args: &Args,
+    sigusr1: &mut tokio::signal::unix::Signal,
+    read_cursor: &mut usize,
+    target_pid: i32,
+) -> Result<(), Box<dyn Error>> {
+    let window = args.window;
+    let total = args.count as i64;
+    let mut bench = Benchmarks::new(window);
+
+    let mut next_seq: i64 = 0;
+    let mut completed: i64 = 0;
+
+    // 预先填满窗口，让最多 window 个 ping 同时在途。发送本身也可能失败（例如高并发下
+    // RLIMIT_SIGPENDING 耗尽），这种 ping 永远等不到回复，就地记为丢失并计入 completed，
+    // 否则下面的 while completed < total 会因为一个从未真正发出的 ping 而永远等下去
+    while next_seq < total && (next_seq as usize) < window {
+        bench.record_start(next_seq);
+        if send_rt_signal_with_value(target_pid, rt_signal(RT_PING_OFFSET), next_seq).is_err() {
+            bench.record_drop();
+            completed += 1;
+        }
+        next_seq += 1;
+    }
+
+    while completed < total {
+        let seq = recv_value(sigusr1, read_cursor).await;
+        if seq < 0 || seq >= total {
+            bench.record_drop();
+            continue;
+        }
+
+        bench.record_finish(seq);
+        completed += 1;
+
+        if next_seq < total {
+            bench.record_start(next_seq);
+            if send_rt_signal_with_value(target_pid, rt_signal(RT_PING_OFFSET), next_seq).is_err() {
+                bench.record_drop();
+                completed += 1;
+            }
+            next_seq += 1;
+        }
+    }
+
+    bench.evaluate(args);
+    Ok(())
+}
+
 async fn run_client(args: &Args) -> Result<(), Box<dyn Error>> {
-    setup_client_signals()?;
-    
-    // 创建信号接收器
-    let mut sigusr2 = signal(SignalKind::user_defined2())?;
-    
-    // 向进程组发送信号（使用PID 0）
+    let server_signal = args.server_signal.to_nix();
+    let client_signal = args.client_signal.to_nix();
+    // 服务器侧的窗口模式强制使用实时信号，客户端的应答信道必须与之保持一致
+    let use_rt = args.realtime || args.window > 1;
+
+    let mut sigusr2 = if use_rt {
+        setup_client_rt_signals()?;
+        signal(SignalKind::from_raw(rt_signal(RT_PING_OFFSET)))?
+    } else {
+        reject_unblockable(args.server_signal)?;
+        reject_unblockable(args.client_signal)?;
+        setup_client_signals(client_signal, server_signal)?;
+        signal(args.client_signal.to_signal_kind())?
+    };
+    let mut read_cursor: usize = 0;
+
+    // 握手前的初始目标：显式指定了 target-pid 就直接用，否则退回到 PID 0（自身进程组）去联系服务器。
+    // 这第一条消息必须用不带负载的 kill(2) 发送——对端 PID 此时还未知，只能走广播寻址，
+    // 而 sigqueue(2) 不支持 pid<=0（见 [`send_queued_signal`]）
+    let initial_target = args.target_pid.unwrap_or(0);
+
     eprintln!("[CLIENT] Sending initial SIGUSR1 to server...");
-    let _send_result = send_signal(0, NixSignal::SIGUSR1);
+    if use_rt {
+        send_raw_signal(initial_target, rt_signal(RT_PONG_OFFSET))?;
+    } else {
+        send_signal(initial_target, server_signal)?;
+    }
     eprintln!("[CLIENT] Sent initial SIGUSR1 to server!");
-    
+
+    // 确定之后通信的目标 PID：显式指定时直接使用；否则等待服务器的回信，从内核填充的
+    // si_pid 中读出服务器的真实 PID（见 run_server 握手部分的说明）
+    let target_pid = if let Some(target_pid) = args.target_pid {
+        target_pid
+    } else {
+        // 以 PID 0 广播的初始信号也会投递给客户端自己，跳过 si_pid 为自身 PID 的条目，直到读到服务器的回信
+        let own_pid = std::process::id() as i32;
+        let server_pid = loop {
+            recv_value(&mut sigusr2, &mut read_cursor).await;
+            let sender_pid = SENDER_PID.load(Ordering::Acquire);
+            if sender_pid != own_pid {
+                break sender_pid;
+            }
+        };
+        eprintln!("[CLIENT] Discovered server PID {} via handshake", server_pid);
+        server_pid
+    };
+
     let mut remaining = args.count;
-    
+
     while remaining > 0 {
-        // 等待来自服务器的信号
-        // eprintln!("[CLIENT] Waiting for SIGUSR2 from server (remaining: {})..", remaining);
-        sigusr2.recv().await;
-        // eprintln!("[CLIENT] Received SIGUSR2 from server (remaining: {})", remaining);
-
-        // 向进程组发送信号（使用PID 0）
-        // eprintln!("[CLIENT] Sending SIGUSR1 to server (remaining: {})..", remaining);
-        let _send_result = send_signal(0, NixSignal::SIGUSR1);
-        // eprintln!("[CLIENT] Sent SIGUSR1 to server (remaining: {})", remaining - 1);
-        
+        if use_rt {
+            // 从队列里取出下一个尚未回复的 ping 序列号原样回传，服务端据此校验是否有消息丢失，
+            // 或匹配窗口模式下的环形缓冲区槽位。window 模式下服务器可能有多个 ping 同时在途，
+            // 用队列逐个取值而不是单次 recv() 配合单槽位读取，才能跟上真实的排队投递次数
+            let seq = recv_value(&mut sigusr2, &mut read_cursor).await;
+            let _send_result = send_rt_signal_with_value(target_pid, rt_signal(RT_PONG_OFFSET), seq);
+        } else if args.payload {
+            // 将收到的 si_value 原样回传，服务端据此校验往返数据是否一致
+            let seq = recv_value(&mut sigusr2, &mut read_cursor).await;
+            let _send_result = send_signal_with_value(target_pid, server_signal, seq);
+        } else {
+            // 等待来自服务器的信号
+            // eprintln!("[CLIENT] Waiting for SIGUSR2 from server (remaining: {})..", remaining);
+            sigusr2.recv().await;
+            // eprintln!("[CLIENT] Received SIGUSR2 from server (remaining: {})", remaining);
+            // eprintln!("[CLIENT] Sending SIGUSR1 to server (remaining: {})..", remaining);
+            let _send_result = send_signal(target_pid, server_signal);
+            // eprintln!("[CLIENT] Sent SIGUSR1 to server (remaining: {})", remaining - 1);
+        }
+
         remaining -= 1;
     }
-    
+
     Ok(())
 }
 